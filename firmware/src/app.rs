@@ -1,6 +1,6 @@
 use crate::bsp::clock::Clocks;
 use crate::bsp::delay::Delay;
-use crate::bsp::gpio::Pins;
+use crate::bsp::gpio::{PinState, Pins};
 
 pub struct App<'a> {
     clocks: Clocks,
@@ -22,6 +22,11 @@ impl<'a> App<'a> {
         self.pins.setup();
 
         self.delay.set_base_clock(&self.clocks);
+
+        if self.clocks.is_degraded() {
+            // Keep the red LED on to indicate degraded mode
+            self.pins.led_r.set_state(PinState::Low);
+        }
     }
 
     pub fn poll(&mut self) {
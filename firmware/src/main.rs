@@ -15,6 +15,9 @@ use hpm_rt::entry;
 
 #[entry]
 fn main() -> ! {
+    #[cfg(feature = "rtt")]
+    bsp::rtt::init();
+
     let gpio0 = unsafe { ral::gpio::GPIO0::instance() };
     let ioc = unsafe { ral::ioc::IOC0::instance() };
     let pioc = unsafe { ral::ioc::PIOC10::instance() };
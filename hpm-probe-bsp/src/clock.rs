@@ -4,6 +4,10 @@ use hpm_ral::{modify_reg, read_reg, write_reg};
 use hpm_ral::{pllctl, sysctl};
 
 const XTAL24M_FREQ: u32 = 24_000_000;
+/// Number of status polls before PLL1 is considered unlocked
+const PLL_LOCK_POLL_COUNT: u32 = 100_000;
+/// Number of busy polls before a clock node switch is considered stalled
+const CLOCK_SWITCH_POLL_COUNT: u32 = 100_000;
 
 pub struct ClockConfigurator {
     pub sysctl: sysctl::SYSCTL,
@@ -15,6 +19,17 @@ impl ClockConfigurator {
         ClockConfigurator { sysctl, pllctl }
     }
 
+    /// Link peripheral clocks and move AHB onto PLL1.
+    ///
+    /// If PLL1 doesn't lock or the AHB switch doesn't complete, every node
+    /// running from PLL1 is moved to osc24 instead and the returned clocks
+    /// report [`Clocks::is_degraded`]. That fallback still relies on the
+    /// 24 MHz crystal, a dead crystal is not covered.
+    ///
+    /// # Safety
+    ///
+    /// Must be called once at boot, before any peripheral using these
+    /// clocks is configured.
     pub unsafe fn freeze(self) -> Clocks {
         // Enable peripheral clocks
         modify_reg!(
@@ -35,18 +50,38 @@ impl ClockConfigurator {
             SPI3: Linked
         );
         modify_reg!(sysctl, self.sysctl, GROUP0_2_VALUE, USBO: Linked);
-        // Set AHB clock source to PLL1 clock 1 and divider to 2 (200 MHz)
-        modify_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_AHB, MUX: 3, DIV: 2);
+        let pll1_locked = self.wait_pll1_lock();
+        let mut clocks = Clocks {
+            sysctl: self.sysctl,
+            pllctl: self.pllctl,
+            degraded: !pll1_locked,
+        };
+
+        if pll1_locked {
+            // Set AHB clock source to PLL1 clock 1 and divider to 2 (200 MHz)
+            clocks.set_clk(ClockName::AHB, ClockSource::Pll1Clock1, 2);
+            clocks.degraded = !clocks.wait_clk_switch(ClockName::AHB);
+        }
+        if clocks.degraded {
+            clocks.leave_pll1();
+        }
+
         // Set UART0 clock source to osc24 and divider to 1 (24 MHz)
-        modify_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_UART0, MUX: 0, DIV: 0);
+        modify_reg!(sysctl, clocks.sysctl, CLOCK_CLK_TOP_UART0, MUX: 0, DIV: 0);
         // Set SPI1 clock source to osc24 and divider to 1 (24 MHz)
-        modify_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_SPI1, MUX: 0, DIV: 0);
-        modify_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_SPI3, MUX: 0, DIV: 0);
+        modify_reg!(sysctl, clocks.sysctl, CLOCK_CLK_TOP_SPI1, MUX: 0, DIV: 0);
+        modify_reg!(sysctl, clocks.sysctl, CLOCK_CLK_TOP_SPI3, MUX: 0, DIV: 0);
 
-        Clocks {
-            sysctl: self.sysctl,
-            pllctl: self.pllctl,
+        clocks
+    }
+
+    fn wait_pll1_lock(&self) -> bool {
+        for _ in 0..PLL_LOCK_POLL_COUNT {
+            if read_reg!(pllctl, self.pllctl, PLL_PLL1_STATUS, PLL_LOCK_COMB) == 1 {
+                return true;
+            }
         }
+        false
     }
 }
 
@@ -69,17 +104,192 @@ pub enum ClockSource {
     Pll4Clock0,
 }
 
-#[derive(Clone, Copy)]
-pub enum ClockName {
-    CPU0,
-    MCHTMR0,
-}
-
 pub struct Clocks {
     sysctl: sysctl::SYSCTL,
     pllctl: pllctl::PLLCTL,
+    degraded: bool,
+}
+
+/// Declares every clock node once.
+///
+/// Nodes listed under a peripheral kind also get a `ClockName::<kind>(n)`
+/// lookup by instance number, in the order they are listed.
+macro_rules! clock_names {
+    (
+        $($NAME:ident => $CLK_TOP:ident),* ;
+        $($kind:ident: [$($INST:ident => $INST_TOP:ident),* $(,)?]),* $(,)?
+    ) => {
+        #[derive(Clone, Copy)]
+        pub enum ClockName {
+            $($NAME,)*
+            $($($INST,)*)*
+        }
+
+        impl ClockName {
+            pub const ALL: &'static [ClockName] = &[
+                $(ClockName::$NAME,)*
+                $($(ClockName::$INST,)*)*
+            ];
+
+            $(
+                #[doc = concat!("Clock node of ", stringify!($kind), " instance `n`")]
+                pub fn $kind(n: usize) -> ClockName {
+                    const NODES: &[ClockName] = &[$(ClockName::$INST),*];
+                    assert!(n < NODES.len());
+                    NODES[n]
+                }
+            )*
+        }
+
+        impl Clocks {
+            fn read_clk_mux(&self, name: ClockName) -> u32 {
+                match name {
+                    $(ClockName::$NAME => read_reg!(sysctl, self.sysctl, $CLK_TOP, MUX),)*
+                    $($(ClockName::$INST => read_reg!(sysctl, self.sysctl, $INST_TOP, MUX),)*)*
+                }
+            }
+
+            fn read_clk_div(&self, name: ClockName) -> u32 {
+                match name {
+                    $(ClockName::$NAME => read_reg!(sysctl, self.sysctl, $CLK_TOP, DIV),)*
+                    $($(ClockName::$INST => read_reg!(sysctl, self.sysctl, $INST_TOP, DIV),)*)*
+                }
+            }
+
+            fn read_clk_busy(&self, name: ClockName) -> bool {
+                match name {
+                    $(ClockName::$NAME => {
+                        read_reg!(sysctl, self.sysctl, $CLK_TOP, GLB_BUSY) == 1
+                            || read_reg!(sysctl, self.sysctl, $CLK_TOP, LOC_BUSY) == 1
+                    })*
+                    $($(ClockName::$INST => {
+                        read_reg!(sysctl, self.sysctl, $INST_TOP, GLB_BUSY) == 1
+                            || read_reg!(sysctl, self.sysctl, $INST_TOP, LOC_BUSY) == 1
+                    })*)*
+                }
+            }
+
+            /// Select the source and divider of a clock node.
+            ///
+            /// The resulting frequency is `src / (div + 1)`.
+            ///
+            /// # Safety
+            ///
+            /// Everything clocked by `name` must be stopped or able to take
+            /// the frequency change, and `src` must be running.
+            pub unsafe fn set_clk(&self, name: ClockName, src: ClockSource, div: u32) {
+                assert!(div < 256);
+                let mux = src as u32;
+                match name {
+                    $(ClockName::$NAME => modify_reg!(sysctl, self.sysctl, $CLK_TOP, MUX: mux, DIV: div),)*
+                    $($(ClockName::$INST => modify_reg!(sysctl, self.sysctl, $INST_TOP, MUX: mux, DIV: div),)*)*
+                }
+            }
+        }
+    };
+}
+
+clock_names!(
+    CPU0 => CLOCK_CLK_TOP_CPU0,
+    MCHTMR0 => CLOCK_CLK_TOP_MCHTMR0,
+    AXI => CLOCK_CLK_TOP_AXI,
+    CONN => CLOCK_CLK_TOP_CONN,
+    AHB => CLOCK_CLK_TOP_AHB;
+    uart: [
+        UART0 => CLOCK_CLK_TOP_UART0,
+        UART1 => CLOCK_CLK_TOP_UART1,
+        UART2 => CLOCK_CLK_TOP_UART2,
+        UART3 => CLOCK_CLK_TOP_UART3,
+        UART4 => CLOCK_CLK_TOP_UART4,
+        UART5 => CLOCK_CLK_TOP_UART5,
+        UART6 => CLOCK_CLK_TOP_UART6,
+        UART7 => CLOCK_CLK_TOP_UART7,
+        UART8 => CLOCK_CLK_TOP_UART8,
+        UART9 => CLOCK_CLK_TOP_UART9,
+        UART10 => CLOCK_CLK_TOP_UART10,
+        UART11 => CLOCK_CLK_TOP_UART11,
+        UART12 => CLOCK_CLK_TOP_UART12,
+        UART13 => CLOCK_CLK_TOP_UART13,
+        UART14 => CLOCK_CLK_TOP_UART14,
+        UART15 => CLOCK_CLK_TOP_UART15,
+    ],
+    spi: [
+        SPI0 => CLOCK_CLK_TOP_SPI0,
+        SPI1 => CLOCK_CLK_TOP_SPI1,
+        SPI2 => CLOCK_CLK_TOP_SPI2,
+        SPI3 => CLOCK_CLK_TOP_SPI3,
+    ],
+    gptmr: [
+        GPTMR0 => CLOCK_CLK_TOP_GPTMR0,
+        GPTMR1 => CLOCK_CLK_TOP_GPTMR1,
+        GPTMR2 => CLOCK_CLK_TOP_GPTMR2,
+        GPTMR3 => CLOCK_CLK_TOP_GPTMR3,
+        GPTMR4 => CLOCK_CLK_TOP_GPTMR4,
+        GPTMR5 => CLOCK_CLK_TOP_GPTMR5,
+        GPTMR6 => CLOCK_CLK_TOP_GPTMR6,
+        GPTMR7 => CLOCK_CLK_TOP_GPTMR7,
+    ],
+);
+
+/// Declares the peripherals that can be linked into clock group 0.
+///
+/// Like `clock_names!`, entries listed under a kind get a
+/// `Resource::<kind>(n)` lookup by instance number.
+macro_rules! resources {
+    (
+        $($NAME:ident => $GROUP:ident, $FIELD:ident),* ;
+        $($kind:ident: [$($INST:ident => $INST_GROUP:ident, $INST_FIELD:ident),* $(,)?]),* $(,)?
+    ) => {
+        #[derive(Clone, Copy)]
+        pub enum Resource {
+            $($NAME,)*
+            $($($INST,)*)*
+        }
+
+        impl Resource {
+            $(
+                #[doc = concat!("Group resource of ", stringify!($kind), " instance `n`")]
+                pub fn $kind(n: usize) -> Resource {
+                    const RESOURCES: &[Resource] = &[$(Resource::$INST),*];
+                    assert!(n < RESOURCES.len());
+                    RESOURCES[n]
+                }
+            )*
+        }
+
+        impl Clocks {
+            /// Link `resource` into clock group 0 so its clock keeps running
+            pub fn link(&self, resource: Resource) {
+                match resource {
+                    $(Resource::$NAME => modify_reg!(sysctl, self.sysctl, $GROUP, $FIELD: Linked),)*
+                    $($(Resource::$INST => modify_reg!(sysctl, self.sysctl, $INST_GROUP, $INST_FIELD: Linked),)*)*
+                }
+            }
+        }
+    };
 }
 
+resources!(
+    GPIO0 => GROUP0_0_VALUE, GPIO0_1,
+    HDMA => GROUP0_0_VALUE, HDMA,
+    CONN => GROUP0_0_VALUE, CONN,
+    MCHTMR0 => GROUP0_0_VALUE, MCHTMR0,
+    UART0 => GROUP0_1_VALUE, UARTO,
+    SPI1 => GROUP0_1_VALUE, SPI1,
+    SPI3 => GROUP0_1_VALUE, SPI3,
+    USB0 => GROUP0_2_VALUE, USBO;
+    gptmr: [
+        GPTMR0 => GROUP0_0_VALUE, TMR0,
+        GPTMR1 => GROUP0_0_VALUE, TMR1,
+        GPTMR2 => GROUP0_0_VALUE, TMR2,
+        GPTMR3 => GROUP0_0_VALUE, TMR3,
+        GPTMR4 => GROUP0_0_VALUE, TMR4,
+        GPTMR5 => GROUP0_0_VALUE, TMR5,
+        GPTMR6 => GROUP0_0_VALUE, TMR6,
+        GPTMR7 => GROUP0_0_VALUE, TMR7,
+    ],
+);
+
 macro_rules! pll_int_freq {
     ($PLLCTL:expr, $PLLx_CFG0:ident, $PLLx_CFG2:ident) => {{
         let refdiv = read_reg!(pllctl, $PLLCTL, $PLLx_CFG0, REFDIV);
@@ -90,6 +300,36 @@ macro_rules! pll_int_freq {
 }
 
 impl Clocks {
+    /// `true` if PLL1 failed to lock or AHB failed to switch onto it, in
+    /// which case every node that was fed by PLL1 now runs from osc24
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Wait for a source or divider change of `name` to take effect,
+    /// returns `false` if it is still busy after the poll budget
+    fn wait_clk_switch(&self, name: ClockName) -> bool {
+        for _ in 0..CLOCK_SWITCH_POLL_COUNT {
+            if !self.read_clk_busy(name) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Move every node fed by PLL1 to osc24 (24 MHz)
+    unsafe fn leave_pll1(&self) {
+        for &name in ClockName::ALL {
+            if matches!(
+                self.get_clk_src(name),
+                ClockSource::Pll1Clock0 | ClockSource::Pll1Clock1
+            ) {
+                self.set_clk(name, ClockSource::Osc0Clock0, 0);
+                self.wait_clk_switch(name);
+            }
+        }
+    }
+
     /// When work in integer mode, the frequency of PLL is:
     ///
     /// $$F_{OUT} = F_{REF} \div REFDIV \times FBDIV\_INT \div POSDIV$$
@@ -117,18 +357,12 @@ impl Clocks {
     }
 
     pub fn get_clk_src(&self, name: ClockName) -> ClockSource {
-        let mux = match name {
-            ClockName::CPU0 => read_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_CPU0, MUX),
-            ClockName::MCHTMR0 => read_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_MCHTMR0, MUX),
-        };
+        let mux = self.read_clk_mux(name);
         unsafe { core::mem::transmute(mux as u8) }
     }
 
     pub fn get_clk_div(&self, name: ClockName) -> u32 {
-        match name {
-            ClockName::CPU0 => read_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_CPU0, DIV),
-            ClockName::MCHTMR0 => read_reg!(sysctl, self.sysctl, CLOCK_CLK_TOP_MCHTMR0, DIV),
-        }
+        self.read_clk_div(name)
     }
 
     pub fn get_clk_freq(&self, name: ClockName) -> u32 {
@@ -144,4 +378,40 @@ impl Clocks {
     pub fn get_clk_mchtmr0_freq(&self) -> u32 {
         self.get_clk_freq(ClockName::MCHTMR0)
     }
+
+    pub fn get_clk_axi_freq(&self) -> u32 {
+        self.get_clk_freq(ClockName::AXI)
+    }
+
+    pub fn get_clk_conn_freq(&self) -> u32 {
+        self.get_clk_freq(ClockName::CONN)
+    }
+
+    pub fn get_clk_ahb_freq(&self) -> u32 {
+        self.get_clk_freq(ClockName::AHB)
+    }
+
+    pub fn get_clk_uart0_freq(&self) -> u32 {
+        self.uart_freq(0)
+    }
+
+    pub fn get_clk_spi1_freq(&self) -> u32 {
+        self.spi_freq(1)
+    }
+
+    pub fn get_clk_spi3_freq(&self) -> u32 {
+        self.spi_freq(3)
+    }
+
+    pub fn uart_freq(&self, n: usize) -> u32 {
+        self.get_clk_freq(ClockName::uart(n))
+    }
+
+    pub fn spi_freq(&self, n: usize) -> u32 {
+        self.get_clk_freq(ClockName::spi(n))
+    }
+
+    pub fn gptmr_freq(&self, n: usize) -> u32 {
+        self.get_clk_freq(ClockName::gptmr(n))
+    }
 }
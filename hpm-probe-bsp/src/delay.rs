@@ -3,6 +3,38 @@ use core::sync::atomic::{AtomicU32, Ordering};
 use hpm_ral::mchtmr;
 use hpm_ral::{modify_reg, read_reg, write_reg};
 
+/// A point in time, in MCHTMR ticks
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant {
+    ticks: u64,
+}
+
+impl Instant {
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn ticks_since(&self, earlier: Instant) -> u64 {
+        self.ticks.saturating_sub(earlier.ticks)
+    }
+}
+
+/// A deadline that can be polled without blocking
+pub struct Timeout<'a> {
+    delay: &'a Delay,
+    deadline: Instant,
+}
+
+impl<'a> Timeout<'a> {
+    pub fn expired(&self) -> bool {
+        self.delay.now() >= self.deadline
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
 pub struct Delay {
     mchtmr: mchtmr::MCHTMR,
     base_clock: AtomicU32,
@@ -74,4 +106,63 @@ impl Delay {
     pub fn get_current(&self) -> u32 {
         read_reg!(mchtmr, self.mchtmr, MTIME) as u32
     }
+
+    /// Full 64-bit MTIME value.
+    ///
+    /// The core reads the two halves separately, so retry until two
+    /// consecutive reads agree on the upper word and don't go backwards.
+    pub fn get_ticks(&self) -> u64 {
+        loop {
+            let first = read_reg!(mchtmr, self.mchtmr, MTIME);
+            let second = read_reg!(mchtmr, self.mchtmr, MTIME);
+            if first >> 32 == second >> 32 && second >= first {
+                break second;
+            }
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        Instant {
+            ticks: self.get_ticks(),
+        }
+    }
+
+    pub fn us_to_ticks(&self, us: u64) -> u64 {
+        let base_clock = self.base_clock.load(Ordering::SeqCst) as u64;
+        assert!(base_clock > 0);
+
+        (us / 1_000_000)
+            .saturating_mul(base_clock)
+            .saturating_add(us % 1_000_000 * base_clock / 1_000_000)
+    }
+
+    pub fn timeout_ticks(&self, ticks: u64) -> Timeout<'_> {
+        Timeout {
+            delay: self,
+            deadline: Instant {
+                ticks: self.get_ticks().saturating_add(ticks),
+            },
+        }
+    }
+
+    pub fn timeout_us(&self, us: u64) -> Timeout<'_> {
+        self.timeout_ticks(self.us_to_ticks(us))
+    }
+
+    pub fn ticks_to_us(&self, ticks: u64) -> u64 {
+        let base_clock = self.base_clock.load(Ordering::SeqCst) as u64;
+        assert!(base_clock > 0);
+
+        ticks / base_clock * 1_000_000 + ticks % base_clock * 1_000_000 / base_clock
+    }
+
+    /// Time since MTIME started counting, in microseconds
+    pub fn uptime_us(&self) -> u64 {
+        self.ticks_to_us(self.get_ticks())
+    }
+
+    /// Time since MTIME started counting, in milliseconds
+    pub fn uptime_ms(&self) -> u64 {
+        self.uptime_us() / 1000
+    }
 }
@@ -15,14 +15,125 @@ pub enum Pull {
     Floating,
 }
 
+#[derive(Clone, Copy)]
+pub enum Speed {
+    Slow = 0,
+    Medium,
+    Fast,
+    Max,
+}
+
+#[derive(Clone, Copy)]
+pub enum SlewRate {
+    Slow = 0,
+    Fast,
+}
+
+/// Output pad settings for a cabling setup
+#[derive(Clone, Copy)]
+pub enum PadProfile {
+    /// Weak drive and slow edges, limits ringing on long ribbon cables
+    LongCable,
+    /// Strong drive and fast edges for short jumpers at high clocks
+    ShortJumper,
+}
+
 pub struct Pin<'a, const PORT: char, const PIN: u8> {
     gpio: &'a gpio::GPIO0,
     ioc: &'a ioc::IOC0,
     pioc: &'a ioc::PIOC10,
 }
 
+/// A set of pins on the same port.
+///
+/// `set_state()` and `toggle()` change every pin in the mask with a single
+/// register write, `write()` needs two.
+pub struct PinMask<'a, const PORT: char> {
+    gpio: &'a gpio::GPIO0,
+    mask: u32,
+}
+
+impl<'a, const PORT: char> PinMask<'a, PORT> {
+    #[inline]
+    pub fn with<const PIN: u8>(mut self, _pin: &Pin<'a, PORT, PIN>) -> Self {
+        self.mask |= 1 << PIN;
+        self
+    }
+
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.mask
+    }
+}
+
+impl<'a, const PORT: char, const PIN: u8> Pin<'a, PORT, PIN> {
+    #[inline]
+    pub fn to_mask(&self) -> PinMask<'a, PORT> {
+        PinMask {
+            gpio: self.gpio,
+            mask: 1 << PIN,
+        }
+    }
+}
+
 macro_rules! impl_port {
     ($port:literal, $OE_VALUE:ident, $DO_SET:ident, $DO_CLEAR:ident, $DO_TOGGLE:ident, $DI_VALUE:ident) => {
+        impl<'a> PinMask<'a, $port> {
+            #[inline]
+            pub fn set_mode_output(&self) -> &Self {
+                let mask = self.mask;
+                modify_reg!(gpio, self.gpio, $OE_VALUE, |r| r | mask);
+                self
+            }
+
+            #[inline]
+            pub fn set_mode_input(&self) -> &Self {
+                let mask = self.mask;
+                modify_reg!(gpio, self.gpio, $OE_VALUE, |r| r & !mask);
+                self
+            }
+
+            #[inline]
+            fn set_high(&self) -> &Self {
+                write_reg!(gpio, self.gpio, $DO_SET, self.mask);
+                self
+            }
+
+            #[inline]
+            fn set_low(&self) -> &Self {
+                write_reg!(gpio, self.gpio, $DO_CLEAR, self.mask);
+                self
+            }
+
+            #[inline]
+            pub fn set_state(&self, state: PinState) -> &Self {
+                match state {
+                    PinState::Low => self.set_low(),
+                    PinState::High => self.set_high(),
+                }
+            }
+
+            #[inline]
+            pub fn toggle(&self) -> &Self {
+                write_reg!(gpio, self.gpio, $DO_TOGGLE, self.mask);
+                self
+            }
+
+            /// Drive the pins in the mask to the matching bits of `value`.
+            ///
+            /// Uses a DO_SET write followed by a DO_CLEAR write, so pins going
+            /// high change one write before pins going low. Where that
+            /// intermediate state matters (e.g. TCK and TDI together), change
+            /// the levels with separate `set_state()` calls in the required
+            /// order.
+            #[inline]
+            pub fn write(&self, value: u32) -> &Self {
+                write_reg!(gpio, self.gpio, $DO_SET, self.mask & value);
+                write_reg!(gpio, self.gpio, $DO_CLEAR, self.mask & !value);
+                self
+            }
+        }
+
         impl<'a, const PIN: u8> Pin<'a, $port, PIN> {
             #[inline]
             pub fn set_mode_output(&self) -> &Self {
@@ -146,6 +257,48 @@ macro_rules! pin {
             pub fn set_pull_floating(&self) -> &Self {
                 self.set_pull(Pull::Floating)
             }
+
+            /// Set pad drive strength, 0 is the weakest and 7 the strongest
+            #[inline]
+            pub fn set_drive_strength(&self, ds: u32) -> &Self {
+                assert!(ds < 8);
+                modify_reg!(ioc, self.ioc, $PAD_CTL, DS: ds);
+                self
+            }
+
+            #[inline]
+            pub fn set_speed(&self, speed: Speed) -> &Self {
+                modify_reg!(ioc, self.ioc, $PAD_CTL, SPD: speed as u32);
+                self
+            }
+
+            #[inline]
+            pub fn set_slew_rate(&self, slew: SlewRate) -> &Self {
+                modify_reg!(ioc, self.ioc, $PAD_CTL, SR: slew as u32);
+                self
+            }
+
+            /// HPM6750 PAD_CTL has no input filter field, hysteresis is
+            /// the only input conditioning a pad offers
+            #[inline]
+            pub fn set_schmitt_trigger(&self, enable: bool) -> &Self {
+                modify_reg!(ioc, self.ioc, $PAD_CTL, SMT: enable as u32);
+                self
+            }
+
+            #[inline]
+            pub fn set_pad_profile(&self, profile: PadProfile) -> &Self {
+                match profile {
+                    PadProfile::LongCable => self
+                        .set_drive_strength(1)
+                        .set_speed(Speed::Slow)
+                        .set_slew_rate(SlewRate::Slow),
+                    PadProfile::ShortJumper => self
+                        .set_drive_strength(7)
+                        .set_speed(Speed::Max)
+                        .set_slew_rate(SlewRate::Fast),
+                }
+            }
         }
     };
 }
@@ -236,5 +389,11 @@ impl<'a> Pins<'a> {
         self.led_r.set_af(0).set_mode_output().set_high();
         self.led_g.set_af(0).set_mode_output().set_high();
         self.led_b.set_af(0).set_mode_output().set_high();
+
+        // These stay at their reset default, a GPIO input, so hysteresis
+        // applies from boot. It only affects the input path.
+        self.pc03.set_schmitt_trigger(true);
+        self.pd14.set_schmitt_trigger(true);
+        self.pd15.set_schmitt_trigger(true);
     }
 }
@@ -1,5 +1,9 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
 pub mod clock;
 pub mod delay;
 pub mod gpio;
+pub mod pwm;
+pub mod ring;
+#[cfg(feature = "rtt")]
+pub mod rtt;
@@ -0,0 +1,130 @@
+use crate::clock::{Clocks, Resource};
+use core::ops::Deref;
+use hpm_ral::gptmr;
+use hpm_ral::{modify_reg, read_reg, write_reg};
+
+/// A GPTMR peripheral instance and its instance number
+pub trait Instance: Deref<Target = gptmr::RegisterBlock> {
+    const N: usize;
+}
+
+macro_rules! instances {
+    ($($GPTMR:ident => $n:literal),* $(,)?) => {
+        $(impl Instance for gptmr::$GPTMR {
+            const N: usize = $n;
+        })*
+    };
+}
+
+instances!(
+    GPTMR0 => 0,
+    GPTMR1 => 1,
+    GPTMR2 => 2,
+    GPTMR3 => 3,
+    GPTMR4 => 4,
+    GPTMR5 => 5,
+    GPTMR6 => 6,
+    GPTMR7 => 7,
+);
+
+/// Edge-aligned PWM on a general purpose timer.
+///
+/// Each channel counts from 0 to its reload value. The output starts high
+/// and goes low once the counter passes the duty value.
+pub struct Pwm<T> {
+    gptmr: T,
+    base_clock: u32,
+}
+
+macro_rules! pwm_channels {
+    ($($CH:ident => $CR:ident, $CMP0:ident, $CMP1:ident, $RLD:ident);* $(;)?) => {
+        #[derive(Clone, Copy)]
+        pub enum Channel {
+            $($CH,)*
+        }
+
+        impl<T: Instance> Pwm<T> {
+            /// Start `channel` with a period of `period` ticks and 0% duty
+            pub fn enable(&self, channel: Channel, period: u32) {
+                assert!(period > 0);
+                match channel {
+                    $(Channel::$CH => {
+                        modify_reg!(gptmr, self.gptmr, $CR, CEN: 0);
+                        write_reg!(gptmr, self.gptmr, $RLD, period);
+                        write_reg!(gptmr, self.gptmr, $CMP0, 0);
+                        write_reg!(gptmr, self.gptmr, $CMP1, period);
+                        modify_reg!(gptmr, self.gptmr, $CR, CMPINIT: 1, CMPEN: 1, CNTRST: 1);
+                        modify_reg!(gptmr, self.gptmr, $CR, CNTRST: 0, CEN: 1);
+                    })*
+                }
+            }
+
+            pub fn disable(&self, channel: Channel) {
+                match channel {
+                    $(Channel::$CH => modify_reg!(gptmr, self.gptmr, $CR, CEN: 0, CMPEN: 0),)*
+                }
+            }
+
+            /// Period of `channel` in ticks, which is also its full duty value
+            pub fn get_max_duty(&self, channel: Channel) -> u32 {
+                match channel {
+                    $(Channel::$CH => read_reg!(gptmr, self.gptmr, $RLD),)*
+                }
+            }
+
+            pub fn get_duty(&self, channel: Channel) -> u32 {
+                match channel {
+                    $(Channel::$CH => read_reg!(gptmr, self.gptmr, $CMP0),)*
+                }
+            }
+
+            /// Set the high time of `channel` in ticks
+            pub fn set_duty(&self, channel: Channel, duty: u32) {
+                // A compare value past the reload value never matches, which
+                // keeps the output high for the whole period
+                let duty = if duty >= self.get_max_duty(channel) {
+                    duty.saturating_add(1)
+                } else {
+                    duty
+                };
+                match channel {
+                    $(Channel::$CH => write_reg!(gptmr, self.gptmr, $CMP0, duty),)*
+                }
+            }
+        }
+    };
+}
+
+pwm_channels!(
+    Ch0 => CHANNEL_CH0_CR, CHANNEL_CH0_CMP0, CHANNEL_CH0_CMP1, CHANNEL_CH0_RLD;
+    Ch1 => CHANNEL_CH1_CR, CHANNEL_CH1_CMP0, CHANNEL_CH1_CMP1, CHANNEL_CH1_RLD;
+    Ch2 => CHANNEL_CH2_CR, CHANNEL_CH2_CMP0, CHANNEL_CH2_CMP1, CHANNEL_CH2_RLD;
+    Ch3 => CHANNEL_CH3_CR, CHANNEL_CH3_CMP0, CHANNEL_CH3_CMP1, CHANNEL_CH3_RLD;
+);
+
+impl<T: Instance> Pwm<T> {
+    /// Link the timer's group clock and take its frequency
+    pub fn new(gptmr: T, clocks: &Clocks) -> Self {
+        clocks.link(Resource::gptmr(T::N));
+        Pwm {
+            gptmr,
+            base_clock: clocks.gptmr_freq(T::N),
+        }
+    }
+
+    /// Start `channel` at `frequency` Hz with 0% duty
+    pub fn enable_frequency(&self, channel: Channel, frequency: u32) {
+        assert!(self.base_clock > 0);
+        assert!(frequency > 0 && frequency <= self.base_clock);
+
+        self.enable(channel, self.base_clock / frequency);
+    }
+
+    /// Set the duty cycle of `channel` in percent
+    pub fn set_duty_percent(&self, channel: Channel, percent: u8) {
+        assert!(percent <= 100);
+
+        let max = self.get_max_duty(channel) as u64;
+        self.set_duty(channel, (max * percent as u64 / 100) as u32);
+    }
+}
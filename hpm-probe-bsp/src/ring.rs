@@ -0,0 +1,317 @@
+use core::cell::UnsafeCell;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Lock-free single-producer single-consumer byte ring buffer.
+///
+/// One slot is kept free to tell a full buffer from an empty one, so at most
+/// `N - 1` bytes can be stored. Data only moves through the [`Producer`] and
+/// [`Consumer`] handles returned by [`split`], so there is exactly one of
+/// each. Either side can be driven by DMA:
+///
+/// - DMA-write / CPU-read: point a circular DMA transfer at
+///   [`Producer::as_mut_ptr`], publish its progress with
+///   [`Producer::set_write_index`] and read with [`Consumer::pop`].
+/// - CPU-write / DMA-read: fill with [`Producer::push`], hand the slice from
+///   [`Consumer::readable`] to a DMA transfer and drop it once the transfer
+///   completes.
+///
+/// [`split`]: RingBuffer::split
+pub struct RingBuffer<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    write: AtomicUsize,
+    read: AtomicUsize,
+}
+
+// Shared access only reads the indices, buffer contents are reached through
+// the split handles
+unsafe impl<const N: usize> Sync for RingBuffer<N> {}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RingBuffer<N> {
+    pub const fn new() -> Self {
+        assert!(N > 1);
+        RingBuffer {
+            buf: UnsafeCell::new([0; N]),
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+
+    pub fn len(&self) -> usize {
+        let write = self.write.load(Ordering::Acquire);
+        let read = self.read.load(Ordering::Acquire);
+        (write + N - read) % N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    pub fn free(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Hand out the only producer and consumer of this buffer
+    pub fn split(&mut self) -> (Producer<'_, N>, Consumer<'_, N>) {
+        let ring = &*self;
+        (Producer { ring }, Consumer { ring })
+    }
+
+    fn storage(&self) -> *mut u8 {
+        self.buf.get() as *mut u8
+    }
+}
+
+/// Write side of a [`RingBuffer`]
+pub struct Producer<'a, const N: usize> {
+    ring: &'a RingBuffer<N>,
+}
+
+impl<'a, const N: usize> Producer<'a, N> {
+    pub fn free(&self) -> usize {
+        self.ring.free()
+    }
+
+    /// Copy as much of `data` as fits, returns the number of bytes stored
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        let write = self.ring.write.load(Ordering::Relaxed);
+        let n = data.len().min(self.ring.free());
+        let first = n.min(N - write);
+
+        let buf = self.ring.storage();
+        unsafe {
+            core::ptr::copy_nonoverlapping(data.as_ptr(), buf.add(write), first);
+            core::ptr::copy_nonoverlapping(data.as_ptr().add(first), buf, n - first);
+        }
+
+        self.ring.write.store((write + n) % N, Ordering::Release);
+        n
+    }
+
+    /// Start of the backing storage, for use as a DMA target.
+    ///
+    /// Whoever writes through it must stay behind the read index, the
+    /// consumer may hold a [`Readable`] over the pending bytes.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ring.storage()
+    }
+
+    /// Publish bytes written by DMA up to `index`.
+    ///
+    /// For a circular transfer `index` is `N` minus the remaining count.
+    pub fn set_write_index(&mut self, index: usize) {
+        assert!(index <= N);
+        self.ring.write.store(index % N, Ordering::Release);
+    }
+}
+
+/// Read side of a [`RingBuffer`]
+pub struct Consumer<'a, const N: usize> {
+    ring: &'a RingBuffer<N>,
+}
+
+impl<'a, const N: usize> Consumer<'a, N> {
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Copy up to `data.len()` bytes out, returns the number of bytes read
+    pub fn pop(&mut self, data: &mut [u8]) -> usize {
+        let read = self.ring.read.load(Ordering::Relaxed);
+        let n = data.len().min(self.ring.len());
+        let first = n.min(N - read);
+
+        let buf = self.ring.storage() as *const u8;
+        unsafe {
+            core::ptr::copy_nonoverlapping(buf.add(read), data.as_mut_ptr(), first);
+            core::ptr::copy_nonoverlapping(buf, data.as_mut_ptr().add(first), n - first);
+        }
+
+        self.ring.read.store((read + n) % N, Ordering::Release);
+        n
+    }
+
+    /// Longest contiguous run of pending bytes, for use as a DMA source.
+    ///
+    /// The bytes are released when the returned guard is dropped, so the
+    /// producer can't reuse them while the slice is alive:
+    ///
+    /// ```compile_fail
+    /// # use hpm_probe_bsp::ring::RingBuffer;
+    /// let mut ring = RingBuffer::<8>::new();
+    /// let (mut tx, mut rx) = ring.split();
+    /// tx.push(b"abc");
+    /// let pending = rx.readable();
+    /// rx.pop(&mut [0; 3]);
+    /// assert_eq!(&*pending, b"abc");
+    /// ```
+    pub fn readable(&mut self) -> Readable<'_, N> {
+        let write = self.ring.write.load(Ordering::Acquire);
+        let read = self.ring.read.load(Ordering::Relaxed);
+        let end = if write >= read { write } else { N };
+
+        Readable {
+            ring: self.ring,
+            start: read,
+            len: end - read,
+            release: end - read,
+        }
+    }
+}
+
+/// Pending bytes borrowed from a [`Consumer`], released on drop
+pub struct Readable<'a, const N: usize> {
+    ring: &'a RingBuffer<N>,
+    start: usize,
+    len: usize,
+    release: usize,
+}
+
+impl<'a, const N: usize> Readable<'a, N> {
+    /// Release only the first `n` bytes, the rest stay pending
+    pub fn release(mut self, n: usize) {
+        assert!(n <= self.len);
+        self.release = n;
+    }
+}
+
+impl<'a, const N: usize> Deref for Readable<'a, N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        let buf = self.ring.storage() as *const u8;
+        unsafe { core::slice::from_raw_parts(buf.add(self.start), self.len) }
+    }
+}
+
+impl<'a, const N: usize> Drop for Readable<'a, N> {
+    fn drop(&mut self) {
+        self.ring
+            .read
+            .store((self.start + self.release) % N, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RingBuffer;
+
+    #[test]
+    fn full_at_capacity() {
+        let mut ring = RingBuffer::<8>::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.capacity(), 7);
+
+        {
+            let (mut tx, _rx) = ring.split();
+            assert_eq!(tx.push(b"0123456789"), 7);
+            assert_eq!(tx.free(), 0);
+            assert_eq!(tx.push(b"x"), 0);
+        }
+        assert!(ring.is_full());
+    }
+
+    #[test]
+    fn push_pop_wraparound() {
+        let mut ring = RingBuffer::<8>::new();
+        let (mut tx, mut rx) = ring.split();
+        let mut out = [0; 8];
+
+        assert_eq!(tx.push(b"abcde"), 5);
+        assert_eq!(rx.pop(&mut out[..3]), 3);
+        assert_eq!(&out[..3], b"abc");
+
+        // Write index wraps past the end of the storage
+        assert_eq!(tx.push(b"fghij"), 5);
+        assert_eq!(rx.len(), 7);
+
+        assert_eq!(rx.pop(&mut out), 7);
+        assert_eq!(&out[..7], b"defghij");
+        assert!(rx.is_empty());
+        assert_eq!(rx.pop(&mut out), 0);
+    }
+
+    #[test]
+    fn readable_across_wrap() {
+        let mut ring = RingBuffer::<8>::new();
+        let (mut tx, mut rx) = ring.split();
+        let mut out = [0; 8];
+
+        tx.push(b"abcdef");
+        rx.pop(&mut out[..5]);
+        tx.push(b"ghij");
+
+        // Pending bytes are "fghij", split at the end of the storage
+        assert_eq!(&*rx.readable(), b"fgh");
+        assert_eq!(&*rx.readable(), b"ij");
+        assert!(rx.is_empty());
+        assert_eq!(&*rx.readable(), b"");
+    }
+
+    #[test]
+    fn readable_partial_release() {
+        let mut ring = RingBuffer::<8>::new();
+        let (mut tx, mut rx) = ring.split();
+
+        tx.push(b"abcd");
+        rx.readable().release(1);
+        assert_eq!(&*rx.readable(), b"bcd");
+    }
+
+    #[test]
+    fn readable_is_not_overwritten_while_held() {
+        let mut ring = RingBuffer::<8>::new();
+        let (mut tx, mut rx) = ring.split();
+
+        tx.push(b"abcdefg");
+        let pending = rx.readable();
+        // Held bytes aren't released yet, so there is no room to overwrite
+        assert_eq!(tx.push(b"XYZXYZX"), 0);
+        assert_eq!(&*pending, b"abcdefg");
+        drop(pending);
+
+        assert_eq!(tx.push(b"XYZXYZX"), 7);
+        assert_eq!(&*rx.readable(), b"X");
+    }
+
+    #[test]
+    fn dma_write_index() {
+        let mut ring = RingBuffer::<8>::new();
+        let (mut tx, mut rx) = ring.split();
+        let mut out = [0; 8];
+
+        unsafe {
+            for (i, b) in b"abcdefgh".iter().enumerate() {
+                tx.as_mut_ptr().add(i).write(*b);
+            }
+        }
+
+        tx.set_write_index(5);
+        assert_eq!(rx.pop(&mut out), 5);
+        assert_eq!(&out[..5], b"abcde");
+
+        // A completed circular transfer reports index N, which wraps to 0
+        tx.set_write_index(8);
+        assert_eq!(rx.len(), 3);
+        assert_eq!(rx.pop(&mut out), 3);
+        assert_eq!(&out[..3], b"fgh");
+    }
+}
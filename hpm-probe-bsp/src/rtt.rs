@@ -0,0 +1,135 @@
+//! SEGGER RTT control block with a single up channel, so the probe's own
+//! diagnostics can be read over its SWD pads without using UART0.
+
+use core::cell::UnsafeCell;
+use core::ptr;
+use core::sync::atomic::{compiler_fence, AtomicU32, Ordering};
+
+const UP_BUFFER_SIZE: usize = 1024;
+/// Drop a write that doesn't fit as a whole instead of blocking
+const MODE_NO_BLOCK_SKIP: u32 = 0;
+
+#[repr(C)]
+struct Channel {
+    name: *const u8,
+    buffer: *mut u8,
+    size: u32,
+    write: AtomicU32,
+    read: AtomicU32,
+    flags: u32,
+}
+
+#[repr(C)]
+struct ControlBlock {
+    id: [u8; 16],
+    max_up_buffers: u32,
+    max_down_buffers: u32,
+    up: Channel,
+}
+
+struct Rtt(UnsafeCell<ControlBlock>);
+
+unsafe impl Sync for Rtt {}
+
+struct UpBuffer(UnsafeCell<[u8; UP_BUFFER_SIZE]>);
+
+unsafe impl Sync for UpBuffer {}
+
+#[no_mangle]
+static _SEGGER_RTT: Rtt = Rtt(UnsafeCell::new(ControlBlock {
+    id: [0; 16],
+    max_up_buffers: 1,
+    max_down_buffers: 0,
+    up: Channel {
+        name: ptr::null(),
+        buffer: ptr::null_mut(),
+        size: 0,
+        write: AtomicU32::new(0),
+        read: AtomicU32::new(0),
+        flags: MODE_NO_BLOCK_SKIP,
+    },
+}));
+
+static UP_BUFFER: UpBuffer = UpBuffer(UnsafeCell::new([0; UP_BUFFER_SIZE]));
+
+/// Fill in the control block.
+///
+/// The ID is written last so a debugger scanning RAM never sees a half
+/// initialized block. It is assembled in pieces, with the space last, so the
+/// full "SEGGER RTT" string never exists in .rodata (which lives in ILM) for
+/// a host to match instead of the real block.
+pub fn init() {
+    let cb = _SEGGER_RTT.0.get();
+    unsafe {
+        (*cb).up.name = c"Terminal".as_ptr() as *const u8;
+        (*cb).up.buffer = UP_BUFFER.0.get() as *mut u8;
+        (*cb).up.size = UP_BUFFER_SIZE as u32;
+        compiler_fence(Ordering::SeqCst);
+
+        let id = (*cb).id.as_mut_ptr();
+        write_id(id.add(7), b"RTT");
+        write_id(id, b"SEGGER");
+        compiler_fence(Ordering::SeqCst);
+        ptr::write_volatile(id.add(6), b' ');
+    }
+}
+
+unsafe fn write_id(dst: *mut u8, part: &[u8]) {
+    for (i, &b) in part.iter().enumerate() {
+        ptr::write_volatile(dst.add(i), b);
+    }
+}
+
+/// Queue `data` on the up channel, returns the number of bytes accepted.
+///
+/// Like the host side expects for [`MODE_NO_BLOCK_SKIP`], `data` is either
+/// queued whole or dropped, so this returns `data.len()` or 0.
+///
+/// # Safety
+///
+/// The write index is updated without a lock, this must not be called from
+/// more than one context at a time, e.g. from both main and an interrupt
+/// handler.
+pub unsafe fn write(data: &[u8]) -> usize {
+    let ch = &(*_SEGGER_RTT.0.get()).up;
+    let size = ch.size as usize;
+    if size == 0 {
+        return 0;
+    }
+
+    let write = ch.write.load(Ordering::Relaxed) as usize;
+    let read = ch.read.load(Ordering::Acquire) as usize;
+    let free = (read + size - write - 1) % size;
+    let n = data.len();
+    if n > free {
+        return 0;
+    }
+    let first = n.min(size - write);
+
+    ptr::copy_nonoverlapping(data.as_ptr(), ch.buffer.add(write), first);
+    ptr::copy_nonoverlapping(data.as_ptr().add(first), ch.buffer, n - first);
+
+    ch.write.store(((write + n) % size) as u32, Ordering::Release);
+    n
+}
+
+/// `core::fmt::Write` adapter over the up channel
+pub struct Writer {
+    _private: (),
+}
+
+impl Writer {
+    /// # Safety
+    ///
+    /// Same as [`write`], only one context may use the up channel at a time.
+    pub unsafe fn new() -> Self {
+        Writer { _private: () }
+    }
+}
+
+impl core::fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        unsafe { write(s.as_bytes()) };
+        Ok(())
+    }
+}